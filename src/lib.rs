@@ -1,3 +1,8 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![cfg_attr(feature = "nightly", feature(trusted_len, min_specialization))]
+
+use ::std::collections::TryReserveError;
+
 pub mod vec;
 
 pub trait SplitSpare<T> {
@@ -9,4 +14,13 @@ pub trait SplitSpare<T> {
 
     /// Convenience function to reserve and then `split_spare`.
     fn reserve_split_spare<'s>(&'s mut self, additional: usize) -> (&'s mut [T], Self::Spare<'s>);
+
+    /// Fallible counterpart to `reserve_split_spare`.
+    ///
+    /// Unlike `reserve_split_spare`, which aborts the process when the allocator fails, this returns
+    /// the allocation error so that OOM-sensitive callers can handle it instead of panicking.
+    fn try_reserve_split_spare<'s>(
+        &'s mut self,
+        additional: usize,
+    ) -> Result<(&'s mut [T], Self::Spare<'s>), TryReserveError>;
 }
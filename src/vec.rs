@@ -1,7 +1,11 @@
 use std::mem::MaybeUninit;
 use std::ptr::NonNull;
 
+#[cfg(feature = "allocator_api")]
+use std::alloc::Allocator;
+
 /// Determines the offset of the len field within a Vec.
+#[cfg(not(feature = "allocator_api"))]
 #[inline]
 fn vec_len_offset_of_val<T>(vec: &mut Vec<T>) -> usize {
     // See https://users.rust-lang.org/t/134050 for why the implementation here is the way it is. We have to work around
@@ -57,9 +61,110 @@ fn vec_len_offset_of_val<T>(vec: &mut Vec<T>) -> usize {
     }
 }
 
+/// Upper bound on the width, in words, of a `Vec<T, A>` that the offset probe can handle. Three words
+/// cover the `RawVec` ptr/cap/len triple; the remainder leave room for a reasonably sized allocator.
+#[cfg(feature = "allocator_api")]
+const MAX_VEC_WORDS: usize = 8;
+
+/// Determines the offset of the len field within a `Vec<T, A>`.
+///
+/// With a custom allocator the struct is wider than the three `RawVec` words, so we cannot rely on
+/// "exactly one word is zero": an allocator field may itself be zero, and the len word may live
+/// anywhere in the layout (e.g. after a non-ZST allocator). Instead we perturb the length and look
+/// for the single word that moves, which pins down the offset wherever it happens to be.
+///
+/// This may reserve, so callers must read `ptr`/`cap` *after* calling it.
+#[cfg(feature = "allocator_api")]
+#[inline]
+fn vec_len_offset_of_val<T, A: Allocator>(vec: &mut Vec<T, A>) -> usize {
+    const {
+        assert!(
+            std::mem::size_of::<Vec<T, A>>()
+                == std::mem::size_of::<Vec<T, A>>() / std::mem::size_of::<usize>()
+                    * std::mem::size_of::<usize>(),
+            "Vec<T, A> is not a whole number of words wide",
+        );
+        assert!(
+            std::mem::size_of::<Vec<T, A>>() >= 3 * std::mem::size_of::<usize>(),
+            "Vec<T, A> is narrower than its three RawVec words",
+        );
+        assert!(
+            std::mem::size_of::<Vec<T, A>>()
+                >= 3 * std::mem::size_of::<usize>() + std::mem::size_of::<A>(),
+            "the allocator field overlaps the three RawVec words",
+        );
+        // The probe reads every word of the struct as a `usize`; an allocator that is not a whole
+        // number of words wide would leave uninitialized padding bytes, and loading those as an
+        // integer is UB. Restrict to word-sized allocators so no padding word is ever read.
+        assert!(
+            std::mem::size_of::<A>()
+                == std::mem::size_of::<A>() / std::mem::size_of::<usize>()
+                    * std::mem::size_of::<usize>(),
+            "the allocator is not a whole number of words wide",
+        );
+        assert!(
+            std::mem::size_of::<Vec<T, A>>() <= MAX_VEC_WORDS * std::mem::size_of::<usize>(),
+            "Vec<T, A> is wider than the offset probe can handle",
+        );
+    }
+
+    let words = std::mem::size_of::<Vec<T, A>>() / std::mem::size_of::<usize>();
+
+    // Reinterpret the vec as holding `MaybeUninit<T>`. This has the same layout, but dropping it is a
+    // no-op, so perturbing the length below can never drop real elements — the same guard the baseline
+    // gets from its `Vec<MaybeUninit<T>>` probe. `MaybeUninit<T>` also keeps `set_len` out of
+    // `clippy::uninit_vec`'s sights.
+    //
+    // SAFETY:
+    // - `MaybeUninit<T>` has the same size and alignment as `T`, so `Vec<T, A>` and
+    //   `Vec<MaybeUninit<T>, A>` have identical layouts.
+    let probe: &mut Vec<MaybeUninit<T>, A> =
+        unsafe { &mut *(vec as *mut Vec<T, A>).cast::<Vec<MaybeUninit<T>, A>>() };
+
+    // Ensure there is at least one spare slot so `orig_len + 1` stays within the capacity. For a ZST
+    // this is a no-op (the capacity is already `usize::MAX`). This may reallocate, so `before` is
+    // captured afterwards.
+    probe.reserve(1);
+
+    let base = (probe as *mut Vec<MaybeUninit<T>, A>).cast::<usize>();
+
+    let mut before = [0usize; MAX_VEC_WORDS];
+    for (i, slot) in before.iter_mut().take(words).enumerate() {
+        *slot = unsafe { *base.add(i) };
+    }
+
+    let orig_len = probe.len();
+
+    // SAFETY:
+    // - `reserve(1)` guarantees `orig_len + 1 <= capacity`; the extra slot stays uninitialized, which
+    //   is sound for a `Vec<MaybeUninit<T>, A>`.
+    unsafe {
+        probe.set_len(orig_len + 1);
+    }
+
+    let mut after = [0usize; MAX_VEC_WORDS];
+    for (i, slot) in after.iter_mut().take(words).enumerate() {
+        *slot = unsafe { *base.add(i) };
+    }
+
+    // Restore the length before computing the offset, so the `expect` below can never unwind while the
+    // length is perturbed.
+    //
+    // SAFETY:
+    // - Restores the length to its original value, undoing the probe.
+    unsafe {
+        probe.set_len(orig_len);
+    }
+
+    (0..words)
+        .find(|&i| before[i] != after[i])
+        .expect("no word changed when the length was perturbed")
+}
+
 /// Safety: changing returned .2 (&mut usize) is considered the same as calling `.set_len(_)`.
 ///
 /// This method provides unique access to all vec parts at once.
+#[cfg(not(feature = "allocator_api"))]
 #[inline]
 unsafe fn vec_split_at_spare_mut_with_len<T>(
     vec: &mut Vec<T>,
@@ -98,6 +203,52 @@ unsafe fn vec_split_at_spare_mut_with_len<T>(
     }
 }
 
+/// Safety: changing returned .2 (&mut usize) is considered the same as calling `.set_len(_)`.
+///
+/// This method provides unique access to all vec parts at once.
+#[cfg(feature = "allocator_api")]
+#[inline]
+unsafe fn vec_split_at_spare_mut_with_len<T, A: Allocator>(
+    vec: &mut Vec<T, A>,
+) -> (&mut [T], &mut [MaybeUninit<T>], &mut usize) {
+    // Probe the len offset first: it may reserve and thus reallocate, so `ptr`/`cap` have to be read
+    // afterwards or they would dangle.
+    let offset = vec_len_offset_of_val(vec);
+
+    let ptr = vec.as_mut_ptr();
+    let len = vec.len();
+    let cap = vec.capacity();
+
+    // SAFETY:
+    // - `ptr` is guaranteed to be valid for `self.len` elements
+    // - but the allocation extends out to `self.buf.capacity()` elements, possibly
+    // uninitialized
+    let spare_ptr = unsafe { ptr.add(len) }.cast::<MaybeUninit<T>>();
+    let spare_len = cap - len;
+
+    // SAFETY:
+    // - The offset returned by vec_len_offset is guaranteed to point to the len field within a Vec<T, A>.
+    let len_mut = unsafe {
+        NonNull::new(vec as *mut Vec<T, A>)
+            .unwrap()
+            .cast::<usize>()
+            .add(offset)
+            .as_mut()
+    };
+
+    // SAFETY:
+    // - `ptr` is guaranteed to be valid for `self.len` elements
+    // - `spare_ptr` is pointing one element past the buffer, so it doesn't overlap with `initialized`
+    // - `len_mut` doesn't overlap with either
+    unsafe {
+        (
+            std::slice::from_raw_parts_mut(ptr, len),
+            std::slice::from_raw_parts_mut(spare_ptr, spare_len),
+            len_mut,
+        )
+    }
+}
+
 /// A copy of `alloc::vec::set_len_on_drop`.
 mod set_len_on_drop {
     // Set the length of the vec when the `SetLenOnDrop` value goes out of scope.
@@ -153,9 +304,47 @@ impl<'a, T> Spare<'a, T> {
         slot.write(item);
         *self.len_mut += 1;
     }
+
+    /// Like `push`, but returns the item back in `Err` instead of panicking when no slot is free.
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        let Some(slot) = self.slots.next() else {
+            return Err(item);
+        };
+        slot.write(item);
+        *self.len_mut += 1;
+        Ok(())
+    }
+
+    /// The number of slots still available to write into.
+    pub fn remaining_capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Like `Extend::extend`, but stops once capacity is exhausted and returns how many items were
+    /// written rather than panicking.
+    ///
+    /// No fitting item is lost: the remaining capacity is checked before each item is pulled, so once
+    /// capacity is exhausted the next item is left in `iter` rather than consumed and discarded. A slot
+    /// is only taken once an item is in hand, so no slot is skipped when `iter` runs out first. The
+    /// caller keeps `iter` and can resume writing into a freshly reserved `Spare`.
+    pub fn try_extend<I: Iterator<Item = T>>(&mut self, iter: &mut I) -> usize {
+        let mut len = SetLenOnDrop::new(self.len_mut);
+        let mut written = 0;
+        while self.slots.len() > 0 {
+            let Some(item) = iter.next() else {
+                break;
+            };
+            let slot = self.slots.next().expect("a slot remains");
+            slot.write(item);
+            len.increment_len(1);
+            written += 1;
+        }
+        written
+    }
 }
 
 impl<T> std::iter::Extend<T> for Spare<'_, T> {
+    #[cfg(not(feature = "nightly"))]
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
         let mut len = SetLenOnDrop::new(self.len_mut);
         for item in iter.into_iter() {
@@ -166,8 +355,60 @@ impl<T> std::iter::Extend<T> for Spare<'_, T> {
             len.increment_len(1);
         }
     }
+
+    #[cfg(feature = "nightly")]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.spec_extend(iter.into_iter());
+    }
+}
+
+/// Specialization backing `Extend for Spare`.
+///
+/// A `TrustedLen` iterator reports an exact length, so the capacity can be checked once up front and
+/// the per-element capacity branch dropped from the write loop.
+#[cfg(feature = "nightly")]
+trait SpecExtend<T, I> {
+    fn spec_extend(&mut self, iter: I);
+}
+
+#[cfg(feature = "nightly")]
+impl<T, I: Iterator<Item = T>> SpecExtend<T, I> for Spare<'_, T> {
+    default fn spec_extend(&mut self, iter: I) {
+        let mut len = SetLenOnDrop::new(self.len_mut);
+        for item in iter {
+            let Some(slot) = self.slots.next() else {
+                panic_exceeded_capacity();
+            };
+            slot.write(item);
+            len.increment_len(1);
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<T, I: Iterator<Item = T> + std::iter::TrustedLen> SpecExtend<T, I> for Spare<'_, T> {
+    fn spec_extend(&mut self, iter: I) {
+        let n = iter
+            .size_hint()
+            .1
+            .expect("TrustedLen iterator reports an exact upper bound");
+        if n > self.slots.len() {
+            panic_exceeded_capacity();
+        }
+
+        let mut len = SetLenOnDrop::new(self.len_mut);
+        for item in iter {
+            // SAFETY:
+            // - `n <= self.slots.len()` was checked above and a `TrustedLen` iterator yields exactly
+            //   `n` items, so a slot is always available here.
+            let slot = unsafe { self.slots.next().unwrap_unchecked() };
+            slot.write(item);
+            len.increment_len(1);
+        }
+    }
 }
 
+#[cfg(not(feature = "allocator_api"))]
 impl<T> crate::SplitSpare<T> for Vec<T> {
     type Spare<'a>
         = Spare<'a, T>
@@ -187,6 +428,44 @@ impl<T> crate::SplitSpare<T> for Vec<T> {
         self.reserve(additional);
         self.split_spare()
     }
+
+    fn try_reserve_split_spare<'s>(
+        &'s mut self,
+        additional: usize,
+    ) -> Result<(&'s mut [T], Self::Spare<'s>), std::collections::TryReserveError> {
+        self.try_reserve(additional)?;
+        Ok(self.split_spare())
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T, A: Allocator> crate::SplitSpare<T> for Vec<T, A> {
+    type Spare<'a>
+        = Spare<'a, T>
+    where
+        Self: 'a;
+
+    fn split_spare<'s>(&'s mut self) -> (&'s mut [T], Self::Spare<'s>) {
+        let (initialized, spare, len_mut) = unsafe { vec_split_at_spare_mut_with_len(self) };
+        let spare = Spare {
+            len_mut,
+            slots: spare.iter_mut(),
+        };
+        (initialized, spare)
+    }
+
+    fn reserve_split_spare<'s>(&'s mut self, additional: usize) -> (&'s mut [T], Self::Spare<'s>) {
+        self.reserve(additional);
+        self.split_spare()
+    }
+
+    fn try_reserve_split_spare<'s>(
+        &'s mut self,
+        additional: usize,
+    ) -> Result<(&'s mut [T], Self::Spare<'s>), std::collections::TryReserveError> {
+        self.try_reserve(additional)?;
+        Ok(self.split_spare())
+    }
 }
 
 #[cfg(test)]
@@ -218,3 +497,66 @@ mod tests {
         spare.extend([1, 2, 3].iter().copied());
     }
 }
+
+#[cfg(all(test, feature = "allocator_api"))]
+mod allocator_api_tests {
+    use crate::SplitSpare;
+    use std::alloc::{AllocError, Allocator, Global, Layout};
+    use std::ptr::NonNull;
+
+    /// A non-ZST allocator that simply forwards to `Global`, widening `Vec<T, A>` beyond the three
+    /// `RawVec` words so the probe has to find the len word among more than three words.
+    #[derive(Clone)]
+    struct Tagged(#[allow(dead_code)] usize);
+
+    unsafe impl Allocator for Tagged {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn global_allocator_spare_works() {
+        let mut vec: Vec<i32> = vec![1, 2, 3];
+
+        let (init, mut spare) = vec.reserve_split_spare(3);
+
+        assert_eq!(init, &[1, 2, 3]);
+
+        spare.extend([4, 5, 6]);
+
+        assert_eq!(vec, &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn non_zst_allocator_spare_works() {
+        let mut vec: Vec<i32, Tagged> = Vec::new_in(Tagged(0xABCD));
+        vec.extend([1, 2, 3]);
+
+        let (init, mut spare) = vec.reserve_split_spare(3);
+
+        assert_eq!(init, &[1, 2, 3]);
+
+        spare.extend([4, 5, 6]);
+
+        assert_eq!(&vec[..], &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn zst_element_non_zst_allocator_spare_works() {
+        let mut vec: Vec<(), Tagged> = Vec::new_in(Tagged(7));
+        vec.extend([(), (), ()]);
+
+        let (init, mut spare) = vec.reserve_split_spare(2);
+
+        assert_eq!(init.len(), 3);
+
+        spare.extend([(), ()]);
+
+        assert_eq!(vec.len(), 5);
+    }
+}